@@ -1,5 +1,6 @@
 use std::convert::TryFrom;
 use std::io::IsTerminal;
+use std::str::FromStr;
 
 use anyhow::{anyhow, bail, Error, Result};
 use serde::{Deserialize, Serialize};
@@ -7,6 +8,9 @@ use tpm2_policy::TPMPolicyStep;
 
 use crate::utils::get_authorized_policy_step;
 
+// A single PCR bank's hash algorithm paired with its selected indices.
+type PcrBankIds = (tss_esapi::interface_types::algorithm::HashingAlgorithm, Vec<u64>);
+
 #[derive(Serialize, Deserialize, std::fmt::Debug)]
 pub(super) struct TPM2Config {
     pub hash: Option<String>,
@@ -17,49 +21,66 @@ pub(super) struct TPM2Config {
     pub pcr_digest: Option<String>,
     // Whether to use a policy. If this is specified without pubkey path or policy path, they get set to defaults
     pub use_policy: Option<bool>,
-    // Public key (in JSON format) for a wildcard policy that's possibly OR'd with the PCR one
-    pub policy_pubkey_path: Option<String>,
-    pub policy_ref: Option<String>,
-    pub policy_path: Option<String>,
+    // Public key (in JSON format) for a wildcard policy that's possibly OR'd with the PCR one.
+    // Each of these can be passed in as a single value or as a JSON array of values, to seal
+    // against multiple independently-signed authorized policy branches. When arrays are used,
+    // policy_pubkey_path, policy_ref and policy_path must all have the same number of entries.
+    pub policy_pubkey_path: Option<serde_json::Value>,
+    pub policy_ref: Option<serde_json::Value>,
+    pub policy_path: Option<serde_json::Value>,
+    // TCTI to use to talk to the TPM, e.g. "device:/dev/tpmrm0" or "swtpm:host=localhost,port=2321".
+    // If unspecified, the tss-esapi default context is used.
+    pub tcti: Option<String>,
+    // JWE content-encryption algorithm, e.g. "A128GCM" or "A256CBC-HS512". Defaults to A256GCM.
+    pub cipher: Option<String>,
 }
 
+// Maximum number of branches the underlying `TPMPolicyStep::Or` can hold.
+const MAX_OR_BRANCHES: usize = 8;
+
 impl TryFrom<&TPM2Config> for TPMPolicyStep {
     type Error = Error;
 
     fn try_from(cfg: &TPM2Config) -> Result<Self> {
-        if cfg.pcr_ids.is_some() && cfg.policy_pubkey_path.is_some() {
-            Ok(TPMPolicyStep::Or([
-                Box::new(TPMPolicyStep::PCRs(
-                    cfg.get_pcr_hash_alg(),
-                    cfg.get_pcr_ids().unwrap(),
-                    Box::new(TPMPolicyStep::NoStep),
-                )),
-                Box::new(get_authorized_policy_step(
-                    cfg.policy_pubkey_path.as_ref().unwrap(),
+        let mut branches: Vec<Box<TPMPolicyStep>> = Vec::new();
+
+        if let Some(bank_ids) = cfg.get_pcr_bank_ids()? {
+            // Chain one PCRs step per bank via the `next` slot: each extends the same running
+            // policy session, so multiple banks end up ANDed together rather than OR'd.
+            let mut step = Box::new(TPMPolicyStep::NoStep);
+            for (hash_alg, ids) in bank_ids.into_iter().rev() {
+                step = Box::new(TPMPolicyStep::PCRs(hash_alg, ids, step));
+            }
+            branches.push(step);
+        }
+
+        if let Some(pubkey_paths) = cfg.get_policy_pubkey_paths() {
+            let policy_refs = cfg.get_policy_refs().unwrap_or_default();
+            if pubkey_paths.len() != policy_refs.len() {
+                bail!("policy_pubkey_path and policy_ref must have the same number of entries");
+            }
+            for (pubkey_path, policy_ref) in pubkey_paths.iter().zip(policy_refs.iter()) {
+                branches.push(Box::new(get_authorized_policy_step(
+                    pubkey_path,
                     &None,
-                    &cfg.policy_ref,
-                )?),
-                Box::new(TPMPolicyStep::NoStep),
-                Box::new(TPMPolicyStep::NoStep),
-                Box::new(TPMPolicyStep::NoStep),
-                Box::new(TPMPolicyStep::NoStep),
-                Box::new(TPMPolicyStep::NoStep),
-                Box::new(TPMPolicyStep::NoStep),
-            ]))
-        } else if cfg.pcr_ids.is_some() {
-            Ok(TPMPolicyStep::PCRs(
-                cfg.get_pcr_hash_alg(),
-                cfg.get_pcr_ids().unwrap(),
-                Box::new(TPMPolicyStep::NoStep),
-            ))
-        } else if cfg.policy_pubkey_path.is_some() {
-            get_authorized_policy_step(
-                cfg.policy_pubkey_path.as_ref().unwrap(),
-                &None,
-                &cfg.policy_ref,
-            )
-        } else {
-            Ok(TPMPolicyStep::NoStep)
+                    &Some(policy_ref.clone()),
+                )?));
+            }
+        }
+
+        match branches.len() {
+            0 => Ok(TPMPolicyStep::NoStep),
+            1 => Ok(*branches.remove(0)),
+            len if len > MAX_OR_BRANCHES => {
+                bail!("Too many policy branches requested (max {MAX_OR_BRANCHES})")
+            }
+            _ => {
+                branches.resize_with(MAX_OR_BRANCHES, || Box::new(TPMPolicyStep::NoStep));
+                let branches: [Box<TPMPolicyStep>; MAX_OR_BRANCHES] = branches
+                    .try_into()
+                    .map_err(|_| anyhow!("Too many policy branches"))?;
+                Ok(TPMPolicyStep::Or(branches))
+            }
         }
     }
 }
@@ -67,6 +88,17 @@ impl TryFrom<&TPM2Config> for TPMPolicyStep {
 pub(crate) const DEFAULT_POLICY_PATH: &str = "/boot/clevis_policy.json";
 pub(crate) const DEFAULT_PUBKEY_PATH: &str = "/boot/clevis_pubkey.json";
 pub(crate) const DEFAULT_POLICY_REF: &str = "";
+pub(crate) const DEFAULT_CIPHER: &str = "A256GCM";
+
+// Supported JWE content-encryption algorithms and their key sizes in bytes.
+const SUPPORTED_CIPHERS: &[(&str, usize)] = &[
+    ("A128GCM", 16),
+    ("A192GCM", 24),
+    ("A256GCM", 32),
+    ("A128CBC-HS256", 32),
+    ("A192CBC-HS384", 48),
+    ("A256CBC-HS512", 64),
+];
 
 impl TPM2Config {
     pub(super) fn get_pcr_hash_alg(
@@ -81,16 +113,63 @@ impl TPM2Config {
         crate::utils::get_hash_alg_from_name(self.hash.as_ref())
     }
 
-    pub(super) fn get_pcr_ids(&self) -> Option<Vec<u64>> {
+    pub(super) fn get_cipher(&self) -> &str {
+        self.cipher.as_deref().unwrap_or(DEFAULT_CIPHER)
+    }
+
+    pub(super) fn get_cipher_key_len(&self) -> usize {
+        SUPPORTED_CIPHERS
+            .iter()
+            .find(|(name, _)| *name == self.get_cipher())
+            .map(|(_, len)| *len)
+            .unwrap_or(32)
+    }
+
+    pub(super) fn get_tcti(&self) -> Result<tss_esapi::tcti_ldr::TctiNameConf> {
+        match &self.tcti {
+            Some(tcti) => tss_esapi::tcti_ldr::TctiNameConf::from_str(tcti)
+                .map_err(|e| anyhow!("Invalid tcti '{tcti}': {e}")),
+            None => Ok(tss_esapi::tcti_ldr::TctiNameConf::from_environment_variable()?),
+        }
+    }
+
+    // Returns one (hash algorithm, PCR indices) pair per PCR bank. The legacy flat form
+    // (`pcr_ids` + `pcr_bank`) normalizes to a single pair; the `{bank: ids}` mapping form
+    // normalizes to one pair per key, letting a policy span multiple banks.
+    pub(super) fn get_pcr_bank_ids(&self) -> Result<Option<Vec<PcrBankIds>>> {
         match &self.pcr_ids {
-            None => None,
-            Some(serde_json::Value::Array(vals)) => {
-                Some(vals.iter().map(|x| x.as_u64().unwrap()).collect())
-            }
-            _ => panic!("Unexpected type found for pcr_ids"),
+            None => Ok(None),
+            Some(serde_json::Value::Array(vals)) => Ok(Some(vec![(
+                self.get_pcr_hash_alg(),
+                vals.iter().map(|x| x.as_u64().unwrap()).collect(),
+            )])),
+            Some(serde_json::Value::Object(map)) => map
+                .iter()
+                .map(|(bank, ids)| match ids {
+                    serde_json::Value::Array(vals) => Ok((
+                        crate::utils::get_hash_alg_from_name(Some(bank)),
+                        vals.iter().map(|x| x.as_u64().unwrap()).collect(),
+                    )),
+                    _ => Err(anyhow!("Unexpected type found for pcr_ids bank '{bank}'")),
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Some),
+            _ => Err(anyhow!("Unexpected type found for pcr_ids")),
         }
     }
 
+    // Back-compat single-bank accessor: flattens the PCR indices from every bank into one
+    // sorted, deduped list, for callers that predate multi-bank support and only expect a
+    // single combined set of indices rather than one per bank.
+    pub(super) fn get_pcr_ids(&self) -> Option<Vec<u64>> {
+        self.get_pcr_bank_ids().ok().flatten().map(|banks| {
+            let mut ids: Vec<u64> = banks.into_iter().flat_map(|(_, ids)| ids).collect();
+            ids.sort_unstable();
+            ids.dedup();
+            ids
+        })
+    }
+
     pub(super) fn get_pcr_ids_str(&self) -> Option<String> {
         match &self.pcr_ids {
             None => None,
@@ -100,25 +179,77 @@ impl TPM2Config {
                     .collect::<Vec<String>>()
                     .join(","),
             ),
+            Some(serde_json::Value::Object(map)) => Some(
+                map.iter()
+                    .map(|(bank, ids)| {
+                        let ids_str = match ids {
+                            serde_json::Value::Array(vals) => vals
+                                .iter()
+                                .map(|x| x.as_u64().unwrap().to_string())
+                                .collect::<Vec<String>>()
+                                .join(","),
+                            _ => String::new(),
+                        };
+                        format!("{bank}:{ids_str}")
+                    })
+                    .collect::<Vec<String>>()
+                    .join("; "),
+            ),
             _ => panic!("Unexpected type found for pcr_ids"),
         }
     }
 
+    pub(super) fn get_policy_pubkey_paths(&self) -> Option<Vec<String>> {
+        Self::get_str_list(&self.policy_pubkey_path)
+    }
+
+    pub(super) fn get_policy_refs(&self) -> Option<Vec<String>> {
+        Self::get_str_list(&self.policy_ref)
+    }
+
+    pub(super) fn get_policy_paths(&self) -> Option<Vec<String>> {
+        Self::get_str_list(&self.policy_path)
+    }
+
+    fn get_str_list(val: &Option<serde_json::Value>) -> Option<Vec<String>> {
+        match val {
+            None => None,
+            Some(serde_json::Value::Array(vals)) => Some(
+                vals.iter()
+                    .map(|x| x.as_str().unwrap().to_string())
+                    .collect(),
+            ),
+            _ => panic!("Unexpected type found for policy field"),
+        }
+    }
+
     fn normalize(mut self) -> Result<TPM2Config> {
         self.normalize_pcr_ids()?;
-        if self.pcr_ids.is_some() && self.pcr_bank.is_none() {
+        if let Some(tcti) = &self.tcti {
+            let trimmed = tcti.trim().to_string();
+            tss_esapi::tcti_ldr::TctiNameConf::from_str(&trimmed)
+                .map_err(|e| anyhow!("Invalid tcti '{trimmed}': {e}"))?;
+            self.tcti = Some(trimmed);
+        }
+        if let Some(cipher) = &self.cipher {
+            if !SUPPORTED_CIPHERS.iter().any(|(name, _)| *name == cipher) {
+                bail!("Unsupported cipher '{cipher}'");
+            }
+        }
+        if matches!(&self.pcr_ids, Some(serde_json::Value::Array(_))) && self.pcr_bank.is_none() {
             self.pcr_bank = Some("sha256".to_string());
         }
         // Make use of the defaults if not specified
         if self.use_policy.is_some() && self.use_policy.unwrap() {
             if self.policy_path.is_none() {
-                self.policy_path = Some(DEFAULT_POLICY_PATH.to_string());
+                self.policy_path = Some(serde_json::Value::String(DEFAULT_POLICY_PATH.to_string()));
             }
             if self.policy_pubkey_path.is_none() {
-                self.policy_pubkey_path = Some(DEFAULT_PUBKEY_PATH.to_string());
+                self.policy_pubkey_path =
+                    Some(serde_json::Value::String(DEFAULT_PUBKEY_PATH.to_string()));
             }
             if self.policy_ref.is_none() {
-                self.policy_ref = Some(DEFAULT_POLICY_REF.to_string());
+                self.policy_ref = Some(serde_json::Value::String(DEFAULT_POLICY_REF.to_string()));
             }
         } else if self.policy_pubkey_path.is_some()
             || self.policy_path.is_some()
@@ -135,63 +266,190 @@ impl TPM2Config {
         {
             bail!("Not all of policy pubkey, path and ref are specified",);
         }
+        Self::normalize_policy_field(&mut self.policy_pubkey_path)?;
+        Self::normalize_policy_field(&mut self.policy_ref)?;
+        Self::normalize_policy_field(&mut self.policy_path)?;
+        if let (Some(pubkey_paths), Some(policy_refs)) =
+            (self.get_policy_pubkey_paths(), self.get_policy_refs())
+        {
+            if pubkey_paths.len() != policy_refs.len() {
+                bail!("policy_pubkey_path and policy_ref must have the same number of entries");
+            }
+            if let Some(policy_paths) = self.get_policy_paths() {
+                if policy_paths.len() != pubkey_paths.len() {
+                    bail!("policy_path must have the same number of entries as policy_pubkey_path");
+                }
+            }
+            let num_branches = pubkey_paths.len() + if self.pcr_ids.is_some() { 1 } else { 0 };
+            if num_branches > MAX_OR_BRANCHES {
+                bail!("Too many policy branches requested (max {MAX_OR_BRANCHES})");
+            }
+        }
         Ok(self)
     }
 
+    // Normalize a policy field (pubkey path, ref, or path) from a bare value to a
+    // single-element array, so that callers always deal with the array form.
+    fn normalize_policy_field(val: &mut Option<serde_json::Value>) -> Result<()> {
+        if let Some(serde_json::Value::String(s)) = val {
+            *val = Some(serde_json::Value::Array(vec![serde_json::Value::String(
+                s.clone(),
+            )]));
+        }
+        match val {
+            None => Ok(()),
+            Some(serde_json::Value::Array(vals)) if vals.iter().all(|v| v.is_string()) => Ok(()),
+            _ => Err(anyhow!("Invalid type for policy field")),
+        }
+    }
+
     fn normalize_pcr_ids(&mut self) -> Result<()> {
-        // Normalize from array with one string to just string
-        if let Some(serde_json::Value::Array(vals)) = &self.pcr_ids {
-            if vals.len() == 1 {
-                if let serde_json::Value::String(val) = &vals[0] {
-                    self.pcr_ids = Some(serde_json::Value::String(val.to_string()));
+        // New form: a JSON object mapping bank name -> indices (same formats as the flat form
+        // below), letting a policy span multiple PCR banks.
+        if matches!(&self.pcr_ids, Some(serde_json::Value::Object(_))) {
+            return self.normalize_pcr_bank_map();
+        }
+
+        // Legacy flat form: pcr_ids is a single comma-separated string or JSON array, combined
+        // with the separate pcr_bank field.
+        let ids = match &self.pcr_ids {
+            None => return Ok(()),
+            Some(val) => Self::expand_pcr_ids_value(val)?,
+        };
+        self.pcr_ids = Some(serde_json::Value::Array(
+            ids.into_iter()
+                .map(|id| serde_json::Value::Number(id.into()))
+                .collect(),
+        ));
+        Ok(())
+    }
+
+    // Canonicalizes the `{bank: ids}` mapping form of pcr_ids: expands each bank's ids, and
+    // collapses back to the flat single-bank representation when only one bank was given, so
+    // downstream code only has to distinguish "one bank" from "several banks".
+    fn normalize_pcr_bank_map(&mut self) -> Result<()> {
+        let map = match self.pcr_ids.take() {
+            Some(serde_json::Value::Object(map)) => map,
+            _ => unreachable!("caller already checked pcr_ids is an Object"),
+        };
+        if map.is_empty() {
+            bail!("pcr_ids object must specify at least one bank");
+        }
+
+        let mut banks = serde_json::Map::new();
+        for (bank, ids) in map {
+            let ids = Self::expand_pcr_ids_value(&ids)?;
+            banks.insert(
+                bank,
+                serde_json::Value::Array(
+                    ids.into_iter()
+                        .map(|id| serde_json::Value::Number(id.into()))
+                        .collect(),
+                ),
+            );
+        }
+
+        if banks.len() == 1 {
+            let (bank, ids) = banks.into_iter().next().unwrap();
+            if let Some(existing) = &self.pcr_bank {
+                if *existing != bank {
+                    bail!(
+                        "pcr_bank '{existing}' conflicts with the bank '{bank}' given in pcr_ids"
+                    );
                 }
             }
+            self.pcr_bank = Some(bank);
+            self.pcr_ids = Some(ids);
+        } else {
+            if self.pcr_bank.is_some() {
+                bail!("pcr_bank cannot be combined with a multi-bank pcr_ids mapping");
+            }
+            self.pcr_bank = None;
+            self.pcr_ids = Some(serde_json::Value::Object(banks));
         }
-        // Normalize pcr_ids from comma-separated string to array
-        if let Some(serde_json::Value::String(val)) = &self.pcr_ids {
-            // Was a string, do a split
-            let newval: Vec<serde_json::Value> = val
-                .split(',')
-                .map(|x| serde_json::Value::String(x.trim().to_string()))
-                .collect();
-            self.pcr_ids = Some(serde_json::Value::Array(newval));
-        }
-        // Normalize pcr_ids from array of Strings to array of Numbers
-        if let Some(serde_json::Value::Array(vals)) = &self.pcr_ids {
-            let newvals: Result<Vec<serde_json::Value>, _> = vals
-                .iter()
-                .map(|x| match x {
-                    serde_json::Value::String(val) => {
-                        match val.trim().parse::<serde_json::Number>() {
-                            Ok(res) => {
-                                let new = serde_json::Value::Number(res);
-                                if !new.is_u64() {
-                                    bail!("Non-positive string int");
-                                }
-                                Ok(new)
-                            }
-                            Err(_) => Err(anyhow!("Unparseable string int")),
-                        }
-                    }
-                    serde_json::Value::Number(n) => {
-                        let new = serde_json::Value::Number(n.clone());
-                        if !new.is_u64() {
-                            return Err(anyhow!("Non-positive int"));
+        Ok(())
+    }
+
+    // Expands a single pcr_ids value (string, number, or array of either) into a sorted,
+    // deduped, range-checked list of PCR indices. Shared by the legacy flat form and each
+    // bank's entry in the `{bank: ids}` mapping form.
+    fn expand_pcr_ids_value(val: &serde_json::Value) -> Result<Vec<u64>> {
+        let mut tokens: Vec<String> = Vec::new();
+        match val {
+            serde_json::Value::String(s) => {
+                tokens.extend(s.split(',').map(|x| x.trim().to_string()))
+            }
+            serde_json::Value::Number(n) => tokens.push(n.to_string()),
+            serde_json::Value::Array(vals) => {
+                for v in vals {
+                    match v {
+                        serde_json::Value::String(s) => {
+                            tokens.extend(s.split(',').map(|x| x.trim().to_string()))
                         }
-                        Ok(new)
+                        serde_json::Value::Number(n) => tokens.push(n.to_string()),
+                        _ => bail!("Invalid value in pcr_ids"),
                     }
-                    _ => Err(anyhow!("Invalid value in pcr_ids")),
-                })
-                .collect();
-            self.pcr_ids = Some(serde_json::Value::Array(newvals?));
+                }
+            }
+            _ => bail!("Invalid type for pcr_ids"),
         }
 
-        match &self.pcr_ids {
-            None => Ok(()),
-            // The normalization above would've caught any non-ints
-            Some(serde_json::Value::Array(_)) => Ok(()),
-            _ => Err(anyhow!("Invalid type")),
+        let mut ids: Vec<u64> = Vec::new();
+        for token in &tokens {
+            ids.extend(Self::expand_pcr_token(token)?);
         }
+        ids.sort_unstable();
+        ids.dedup();
+        if let Some(bad) = ids.iter().find(|&&id| id > Self::MAX_PCR_INDEX) {
+            bail!(
+                "PCR index {bad} out of range (must be 0-{})",
+                Self::MAX_PCR_INDEX
+            );
+        }
+        Ok(ids)
+    }
+
+    // Highest valid PCR index (a TPM2 has 24 PCRs per bank, numbered 0-23).
+    const MAX_PCR_INDEX: u64 = 23;
+
+    // Named aliases for conventional groups of PCRs, usable alongside plain indices and
+    // "start-end" ranges in a pcr_ids string or array.
+    const PCR_ALIASES: &'static [(&'static str, &'static [u64])] = &[
+        ("firmware", &[0, 1]),
+        ("bootloader", &[4, 5]),
+        ("secureboot", &[7]),
+        ("kernel", &[8, 9]),
+    ];
+
+    fn expand_pcr_token(token: &str) -> Result<Vec<u64>> {
+        let token = token.trim();
+        if let Some((_, ids)) = Self::PCR_ALIASES.iter().find(|(name, _)| *name == token) {
+            return Ok(ids.to_vec());
+        }
+        if let Some((start, end)) = token.split_once('-') {
+            let start: u64 = start
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid PCR range '{token}'"))?;
+            let end: u64 = end
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid PCR range '{token}'"))?;
+            if start > end {
+                bail!("Invalid PCR range '{token}': start must not be greater than end");
+            }
+            if end > Self::MAX_PCR_INDEX {
+                bail!(
+                    "PCR range '{token}' out of range (must be 0-{})",
+                    Self::MAX_PCR_INDEX
+                );
+            }
+            return Ok((start..=end).collect());
+        }
+        token
+            .parse::<u64>()
+            .map(|id| vec![id])
+            .map_err(|_| anyhow!("Invalid PCR id '{token}'"))
     }
 }
 
@@ -201,6 +459,8 @@ pub(super) enum ActionMode {
     Decrypt,
     Summary,
     Help,
+    // Dry-run: parse a config and print the policy it would produce, without sealing anything.
+    Explain,
 }
 
 pub(super) fn get_mode_and_cfg(args: &[String]) -> Result<(ActionMode, Option<TPM2Config>)> {
@@ -210,6 +470,13 @@ pub(super) fn get_mode_and_cfg(args: &[String]) -> Result<(ActionMode, Option<TP
     if args.len() > 1 && args[1] == "--help" {
         return Ok((ActionMode::Help, None));
     }
+    if args.len() > 1 && args[1] == "--policy" {
+        if args.len() < 3 {
+            bail!("--policy requires a config argument");
+        }
+        let cfg = serde_json::from_str::<TPM2Config>(&args[2])?.normalize()?;
+        return Ok((ActionMode::Explain, Some(cfg)));
+    }
     if std::io::stdin().is_terminal() {
         return Ok((ActionMode::Help, None));
     }
@@ -236,3 +503,175 @@ pub(super) fn get_mode_and_cfg(args: &[String]) -> Result<(ActionMode, Option<TP
 
     Ok((mode, cfg))
 }
+
+// Prints a human-readable rendering of the policy a config would produce, for `ActionMode::Explain`.
+pub(super) fn explain_policy(cfg: &TPM2Config) -> Result<()> {
+    match cfg.get_pcr_bank_ids()? {
+        Some(banks) => {
+            for (hash_alg, ids) in banks {
+                println!("PCR bank {hash_alg:?}: {ids:?}");
+            }
+        }
+        None => println!("PCR bank: (none)"),
+    }
+
+    match cfg.get_policy_pubkey_paths() {
+        Some(pubkey_paths) => {
+            let policy_refs = cfg.get_policy_refs().unwrap_or_default();
+            println!("Authorized policy branches:");
+            for (pubkey_path, policy_ref) in pubkey_paths.iter().zip(policy_refs.iter()) {
+                println!("  - pubkey: {pubkey_path}, policy_ref: {policy_ref:?}");
+            }
+        }
+        None => println!("Authorized policy branches: (none)"),
+    }
+
+    println!(
+        "JWE content-encryption algorithm: {} ({}-byte key)",
+        cfg.get_cipher(),
+        cfg.get_cipher_key_len()
+    );
+
+    let step = match TPMPolicyStep::try_from(cfg) {
+        Ok(step) => step,
+        Err(e) => {
+            println!("Policy digest unavailable (failed to build policy: {e})");
+            return Ok(());
+        }
+    };
+    match compute_policy_digest(cfg, &step) {
+        Ok(digest) => println!("Computed policy digest: {digest}"),
+        Err(e) => println!("Policy digest unavailable (no TPM reachable?): {e}"),
+    }
+
+    Ok(())
+}
+
+fn compute_policy_digest(cfg: &TPM2Config, step: &TPMPolicyStep) -> Result<String> {
+    let mut context = tss_esapi::Context::new(cfg.get_tcti()?)?;
+    let session = step.send_policy(&mut context, true)?;
+    let digest = context.policy_get_digest(session)?;
+    Ok(digest
+        .as_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn empty_cfg() -> TPM2Config {
+        TPM2Config {
+            hash: None,
+            key: None,
+            pcr_bank: None,
+            pcr_ids: None,
+            pcr_digest: None,
+            use_policy: None,
+            policy_pubkey_path: None,
+            policy_ref: None,
+            policy_path: None,
+            tcti: None,
+            cipher: None,
+        }
+    }
+
+    #[test]
+    fn pcr_range_expands_inclusive() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!("5-8"));
+        cfg.normalize_pcr_ids().unwrap();
+        assert_eq!(cfg.pcr_ids, Some(json!([5, 6, 7, 8])));
+    }
+
+    #[test]
+    fn pcr_range_rejects_inverted_bounds() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!("5-2"));
+        assert!(cfg.normalize_pcr_ids().is_err());
+    }
+
+    #[test]
+    fn pcr_id_rejects_out_of_range_index() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!("30"));
+        assert!(cfg.normalize_pcr_ids().is_err());
+    }
+
+    // Regression test: a huge range end must be rejected before the range is materialized
+    // into a Vec, not after (otherwise this allocates ~2^64 elements and aborts the process).
+    #[test]
+    fn pcr_range_rejects_huge_end_without_allocating() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!("1-18446744073709551614"));
+        assert!(cfg.normalize_pcr_ids().is_err());
+    }
+
+    #[test]
+    fn pcr_alias_mixes_with_plain_index() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!("firmware,10"));
+        cfg.normalize_pcr_ids().unwrap();
+        assert_eq!(cfg.pcr_ids, Some(json!([0, 1, 10])));
+    }
+
+    #[test]
+    fn single_bank_pcr_ids_object_collapses_to_flat_form() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!({"sha256": "0,1"}));
+        cfg.normalize_pcr_ids().unwrap();
+        assert_eq!(cfg.pcr_bank, Some("sha256".to_string()));
+        assert_eq!(cfg.pcr_ids, Some(json!([0, 1])));
+    }
+
+    #[test]
+    fn multi_bank_pcr_ids_object_stays_a_mapping() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!({"sha1": "0", "sha256": "1-2"}));
+        cfg.normalize_pcr_ids().unwrap();
+        assert_eq!(cfg.pcr_bank, None);
+        assert_eq!(cfg.pcr_ids, Some(json!({"sha1": [0], "sha256": [1, 2]})));
+    }
+
+    #[test]
+    fn conflicting_pcr_bank_and_single_bank_mapping_is_rejected() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_bank = Some("sha1".to_string());
+        cfg.pcr_ids = Some(json!({"sha256": "0,1"}));
+        assert!(cfg.normalize_pcr_ids().is_err());
+    }
+
+    #[test]
+    fn get_pcr_ids_flattens_across_banks() {
+        let mut cfg = empty_cfg();
+        cfg.pcr_ids = Some(json!({"sha1": "0", "sha256": "1-2"}));
+        cfg.normalize_pcr_ids().unwrap();
+        assert_eq!(cfg.get_pcr_ids(), Some(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn unsupported_cipher_is_rejected() {
+        let mut cfg = empty_cfg();
+        cfg.cipher = Some("AES-NOPE".to_string());
+        assert!(cfg.normalize().is_err());
+    }
+
+    #[test]
+    fn invalid_tcti_is_rejected() {
+        let mut cfg = empty_cfg();
+        cfg.tcti = Some("not-a-real-tcti".to_string());
+        assert!(cfg.normalize().is_err());
+    }
+
+    #[test]
+    fn policy_fields_must_line_up_pairwise() {
+        let mut cfg = empty_cfg();
+        cfg.use_policy = Some(true);
+        cfg.policy_pubkey_path = Some(json!(["a.pub", "b.pub"]));
+        cfg.policy_ref = Some(json!(["only-one"]));
+        assert!(cfg.normalize().is_err());
+    }
+}